@@ -0,0 +1,111 @@
+//! Generates the opcode constants and decode tables from `instructions.in`
+//! so the instruction set has exactly one source of truth instead of being
+//! hand-duplicated across `step`, `asm` and `disasm`.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+struct Instruction {
+    mnemonic: String,
+    opcode: u8,
+    format: &'static str,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=instructions.in");
+
+    let spec = fs::read_to_string("instructions.in").expect("could not read instructions.in");
+    let instructions = parse(&spec);
+
+    let mut out = String::new();
+
+    for instr in &instructions {
+        writeln!(out, "pub(crate) const OP_{}: u8 = {:#04x};", instr.mnemonic, instr.opcode).unwrap();
+    }
+    writeln!(out).unwrap();
+
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]").unwrap();
+    writeln!(out, "pub(crate) enum OperandFormat {{").unwrap();
+    writeln!(out, "    None,").unwrap();
+    writeln!(out, "    Reg1,").unwrap();
+    writeln!(out, "    Reg2,").unwrap();
+    writeln!(out, "    Reg1Addr,").unwrap();
+    writeln!(out, "    Reg2Addr,").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "impl OperandFormat {{").unwrap();
+    writeln!(out, "    pub(crate) fn takes_addr(self) -> bool {{").unwrap();
+    writeln!(out, "        matches!(self, OperandFormat::Reg1Addr | OperandFormat::Reg2Addr)").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "pub(crate) fn opcode_name(opcode: u8) -> &'static str {{").unwrap();
+    writeln!(out, "    match opcode {{").unwrap();
+    for instr in &instructions {
+        writeln!(out, "        {:#04x} => \"{}\",", instr.opcode, instr.mnemonic).unwrap();
+    }
+    writeln!(out, "        _ => \"???\",").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "pub(crate) fn opcode_for(name: &str) -> Option<u8> {{").unwrap();
+    writeln!(out, "    match name.to_ascii_uppercase().as_str() {{").unwrap();
+    for instr in &instructions {
+        writeln!(out, "        \"{}\" => Some({:#04x}),", instr.mnemonic, instr.opcode).unwrap();
+    }
+    writeln!(out, "        _ => None,").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "pub(crate) fn operand_format(opcode: u8) -> OperandFormat {{").unwrap();
+    writeln!(out, "    match opcode {{").unwrap();
+    for instr in &instructions {
+        writeln!(out, "        {:#04x} => OperandFormat::{},", instr.opcode, format_variant(instr.format)).unwrap();
+    }
+    writeln!(out, "        _ => OperandFormat::None,").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("instructions.rs"), out).unwrap();
+}
+
+fn parse(spec: &str) -> Vec<Instruction> {
+    spec.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let mnemonic = fields.next().expect("missing mnemonic").to_string();
+            let opcode_text = fields.next().expect("missing opcode");
+            let opcode = u8::from_str_radix(opcode_text.trim_start_matches("0x"), 16)
+                .expect("opcode is not valid hex");
+            let format = match fields.next().expect("missing format") {
+                "none" => "none",
+                "reg1" => "reg1",
+                "reg2" => "reg2",
+                "reg1addr" => "reg1addr",
+                "reg2addr" => "reg2addr",
+                other => panic!("unknown operand format: {}", other),
+            };
+            Instruction { mnemonic, opcode, format }
+        })
+        .collect()
+}
+
+fn format_variant(format: &str) -> &'static str {
+    match format {
+        "none" => "None",
+        "reg1" => "Reg1",
+        "reg2" => "Reg2",
+        "reg1addr" => "Reg1Addr",
+        "reg2addr" => "Reg2Addr",
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use thingamajig::{BufferHost, Core};
+
+/// Treats the first half of the fuzz input as a program to load and the
+/// second half as scripted stdin. Any panic or out-of-bounds access inside
+/// `run_bounded` aborts the run and is reported by libFuzzer as a crash, so
+/// this only needs to run the loaded bytes.
+fuzz_target!(|data: &[u8]| {
+    let split = data.len() / 2;
+    let (program, input) = data.split_at(split);
+
+    let mut core = Core::new(BufferHost::new(input));
+    core.load(program);
+    core.run_bounded(10_000);
+});
@@ -0,0 +1,197 @@
+//! A small line-oriented assembler for the VM's bytecode.
+//!
+//! Each instruction occupies one byte (opcode in the high nibble, up to two
+//! register operands in the low nibble) optionally followed by a big-endian
+//! 16-bit address, matching `Core::next_short`. Labels are resolved to
+//! addresses in a second pass once every instruction's size is known.
+//!
+//! Mnemonics and operand counts come from the `instructions.in`-generated
+//! `opcode_for`/`operand_format` tables, so this stays in sync with `step`
+//! and `disasm` automatically.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{opcode_for, operand_format, OperandFormat, OP_BREQ};
+
+/// An address operand, either a literal or a forward/backward label
+/// reference resolved once every label's address is known.
+enum Addr {
+    Literal(u16),
+    Label(String),
+}
+
+enum Item {
+    Instruction { opcode: u8, r_a: u8, r_b: u8, addr: Option<Addr> },
+    Byte(u8),
+}
+
+struct Placed {
+    addr: u16,
+    item: Item,
+}
+
+/// Length in bytes of the unconditional jump stub prepended at address 0
+/// when `.entry` points somewhere else.
+const ENTRY_STUB_LEN: u16 = 3;
+
+/// Assembles `source` into a byte blob consumable by `Core::load`.
+pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+    let mut symbols = BTreeMap::new();
+    let mut entry: Option<String> = None;
+    let mut placed = Vec::new();
+
+    // The VM always starts executing at address 0. If the source declares
+    // an `.entry`, reserve `ENTRY_STUB_LEN` bytes there up front for an
+    // unconditional jump to it, rather than assembling normally and then
+    // shifting everything along: a post-hoc shift would drag `.org`-anchored
+    // content away from the absolute address the user asked for, and could
+    // wrap addresses placed near the top of memory.
+    let wants_entry_stub = source
+        .lines()
+        .any(|line| strip_comment(line).split_whitespace().next() == Some(".entry"));
+    let mut cursor: u16 = if wants_entry_stub { ENTRY_STUB_LEN } else { 0 };
+
+    for (lineno, raw_line) in source.lines().enumerate() {
+        let lineno = lineno + 1;
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(label) = line.strip_suffix(':') {
+            symbols.insert(label.trim().to_string(), cursor);
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let mnemonic = tokens.next().ok_or_else(|| format!("line {}: empty line", lineno))?;
+
+        match mnemonic {
+            ".org" => {
+                let value = tokens.next().ok_or_else(|| format!("line {}: .org needs an address", lineno))?;
+                cursor = parse_number(value).ok_or_else(|| format!("line {}: bad address {}", lineno, value))?;
+                continue;
+            }
+            ".byte" => {
+                for value in tokens {
+                    let byte = parse_number(value)
+                        .ok_or_else(|| format!("line {}: bad byte {}", lineno, value))?;
+                    placed.push(Placed { addr: cursor, item: Item::Byte(byte as u8) });
+                    cursor = cursor.wrapping_add(1);
+                }
+                continue;
+            }
+            ".entry" => {
+                let label = tokens.next().ok_or_else(|| format!("line {}: .entry needs a label", lineno))?;
+                entry = Some(label.to_string());
+                continue;
+            }
+            _ => {}
+        }
+
+        let opcode = opcode_for(mnemonic).ok_or_else(|| format!("line {}: unknown mnemonic {}", lineno, mnemonic))?;
+        let rest: Vec<&str> = tokens.collect();
+        let (r_a, r_b, addr) = parse_operands(opcode, &rest, lineno)?;
+
+        let format = operand_format(opcode);
+        let size = 1 + if format.takes_addr() { 2 } else { 0 };
+        placed.push(Placed { addr: cursor, item: Item::Instruction { opcode, r_a, r_b, addr } });
+        cursor = cursor.wrapping_add(size);
+    }
+
+    // If the entry label didn't land on address 0 (the reservation above
+    // starts the cursor at ENTRY_STUB_LEN, so it is at 0 only if the source
+    // explicitly `.org`'d it there), write the stub: `BREQ r0 r0 <addr>` always branches since
+    // r0 == r0, and unlike `CREQ` it doesn't set `rp`, so it's a plain jump
+    // rather than a call.
+    let mut out = Vec::new();
+    if let Some(label) = &entry {
+        let target = *symbols.get(label).ok_or_else(|| format!("undefined entry label {}", label))?;
+        if target != 0 {
+            out = vec![0u8; ENTRY_STUB_LEN as usize];
+            out[0] = OP_BREQ << 4;
+            out[1..3].copy_from_slice(&target.to_be_bytes());
+        }
+    }
+
+    for p in &placed {
+        let at = p.addr as usize;
+        let end = at + if matches!(p.item, Item::Instruction { ref addr, .. } if addr.is_some()) { 3 } else { 1 };
+        if out.len() < end {
+            out.resize(end, 0);
+        }
+        match &p.item {
+            Item::Byte(b) => out[at] = *b,
+            Item::Instruction { opcode, r_a, r_b, addr } => {
+                out[at] = (opcode << 4) | (r_a << 2) | r_b;
+                if let Some(operand) = addr {
+                    let target = match operand {
+                        Addr::Literal(n) => *n,
+                        Addr::Label(name) => *symbols
+                            .get(name)
+                            .ok_or_else(|| format!("undefined label {}", name))?,
+                    };
+                    out[at + 1..at + 3].copy_from_slice(&target.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find(';') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn parse_number(s: &str) -> Option<u16> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+fn parse_reg(s: &str) -> Option<u8> {
+    let digit = s.strip_prefix('r').or_else(|| s.strip_prefix('R'))?;
+    let n: u8 = digit.parse().ok()?;
+    if n < 4 { Some(n) } else { None }
+}
+
+/// Parses the register/address operands for `opcode` out of the remaining
+/// whitespace-separated tokens on the line.
+fn parse_operands(opcode: u8, tokens: &[&str], lineno: usize) -> Result<(u8, u8, Option<Addr>), String> {
+    let format = operand_format(opcode);
+    let reg_count = match format {
+        OperandFormat::None => 0,
+        OperandFormat::Reg1 | OperandFormat::Reg1Addr => 1,
+        OperandFormat::Reg2 | OperandFormat::Reg2Addr => 2,
+    };
+
+    let mut regs = [0u8; 2];
+    for (i, slot) in regs.iter_mut().enumerate().take(reg_count) {
+        let tok = tokens.get(i).ok_or_else(|| format!("line {}: missing register operand", lineno))?;
+        *slot = parse_reg(tok).ok_or_else(|| format!("line {}: bad register {}", lineno, tok))?;
+    }
+
+    let addr = if format.takes_addr() {
+        let tok = tokens
+            .get(reg_count)
+            .ok_or_else(|| format!("line {}: missing address operand", lineno))?;
+        Some(match parse_number(tok) {
+            Some(n) => Addr::Literal(n),
+            None => Addr::Label(tok.to_string()),
+        })
+    } else {
+        None
+    };
+
+    Ok((regs[0], regs[1], addr))
+}
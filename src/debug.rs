@@ -0,0 +1,97 @@
+//! An interactive single-step debugger, driven from the command line with
+//! `--debug`. Drops into a prompt before every `step`, showing the
+//! disassembled current instruction and the register file, and accepts a
+//! handful of commands for controlling execution.
+
+use std::io::{stdin, stdout, Write};
+
+use thingamajig::{disasm, Core, Host};
+
+/// Runs `core` under the interactive debugger until it halts or the user
+/// quits.
+pub fn run<H: Host + 'static>(core: &mut Core<H>) {
+    let mut breakpoints: Vec<u16> = Vec::new();
+
+    println!("thingamajig debugger. Commands: s(tep), c(ontinue), b <addr>, m <addr> [value], q(uit)");
+
+    'outer: while !core.is_halted() {
+        if breakpoints.contains(&core.ip()) {
+            println!("breakpoint hit at {:#06x}", core.ip());
+        }
+
+        print_state(core);
+
+        loop {
+            print!("(dbg) ");
+            stdout().flush().unwrap();
+
+            let mut line = String::new();
+            if stdin().read_line(&mut line).unwrap() == 0 {
+                break 'outer;
+            }
+
+            match line.trim() {
+                "" | "s" | "step" => {
+                    step(core);
+                    break;
+                }
+                "c" | "continue" => {
+                    step(core);
+                    while !core.is_halted() && !breakpoints.contains(&core.ip()) {
+                        step(core);
+                    }
+                    break;
+                }
+                "q" | "quit" => break 'outer,
+                cmd if cmd.starts_with('b') => {
+                    match parse_addr(cmd.trim_start_matches('b').trim()) {
+                        Some(addr) => {
+                            breakpoints.push(addr);
+                            println!("breakpoint set at {:#06x}", addr);
+                        }
+                        None => println!("usage: b <addr>"),
+                    }
+                }
+                cmd if cmd.starts_with('m') => {
+                    let rest = cmd.trim_start_matches('m').trim();
+                    let mut parts = rest.split_whitespace();
+                    match parts.next().and_then(parse_addr) {
+                        Some(addr) => match parts.next().and_then(parse_addr) {
+                            Some(value) => {
+                                core.poke(addr, value as u8);
+                                println!("{:#06x} <- {:#04x}", addr, value as u8);
+                            }
+                            None => println!("{:#06x}: {:#04x}", addr, core.peek(addr)),
+                        },
+                        None => println!("usage: m <addr> [value]"),
+                    }
+                }
+                _ => println!("unknown command"),
+            }
+        }
+    }
+
+    if core.is_halted() {
+        println!("halted at {:#06x}", core.ip());
+    }
+}
+
+fn step<H: Host + 'static>(core: &mut Core<H>) {
+    if let Err(trap) = core.step() {
+        println!("trap: {:?} at ip={:#06x}", trap, core.trap_register());
+    }
+}
+
+fn print_state<H: Host + 'static>(core: &Core<H>) {
+    let (text, _) = disasm::decode_one(core.memory(), core.ip());
+    println!("{:#06x}  {}", core.ip(), text);
+    println!("{}", core.register_snapshot());
+}
+
+fn parse_addr(s: &str) -> Option<u16> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
@@ -0,0 +1,134 @@
+//! Memory-mapped devices living on the `DeviceBus`.
+//!
+//! Each device owns a small range of addresses in the VM's address space.
+//! `DeviceBus::read`/`write` dispatch to whichever device's range contains
+//! the address, passing through the offset within that range as `reg`, so
+//! adding a peripheral is just another `attach` call instead of a new match
+//! arm in `Core::mem_read`/`mem_write`.
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::RangeInclusive;
+
+use crate::trap::Trap;
+use crate::Host;
+
+/// A single memory-mapped peripheral.
+pub trait Device {
+    /// Reads register `reg` (offset within this device's address range).
+    fn read(&mut self, reg: u16) -> Result<u8, Trap>;
+    /// Writes register `reg` (offset within this device's address range).
+    fn write(&mut self, reg: u16, value: u8) -> Result<(), Trap>;
+    /// Called once per executed instruction. Devices that run independently
+    /// of guest I/O (like a free-running timer) override this.
+    fn tick(&mut self) {}
+}
+
+/// Dispatches reads/writes in the MMIO window to the device whose range
+/// covers the address, checking devices in the order they were attached.
+pub struct DeviceBus {
+    devices: Vec<(RangeInclusive<u16>, Box<dyn Device>)>,
+}
+
+impl DeviceBus {
+    pub fn new() -> Self {
+        Self { devices: Vec::new() }
+    }
+
+    pub fn attach(&mut self, range: RangeInclusive<u16>, device: Box<dyn Device>) {
+        self.devices.push((range, device));
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        self.devices.iter().any(|(range, _)| range.contains(&addr))
+    }
+
+    pub fn read(&mut self, addr: u16) -> Result<u8, Trap> {
+        for (range, device) in &mut self.devices {
+            if range.contains(&addr) {
+                return device.read(addr - range.start());
+            }
+        }
+        Err(Trap::MemoryFault)
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) -> Result<(), Trap> {
+        for (range, device) in &mut self.devices {
+            if range.contains(&addr) {
+                return device.write(addr - range.start(), value);
+            }
+        }
+        Err(Trap::MemoryFault)
+    }
+
+    pub fn tick(&mut self) {
+        for (_, device) in &mut self.devices {
+            device.tick();
+        }
+    }
+}
+
+/// A free-running timer: a 16-bit counter that increments once per executed
+/// `step` and wraps around at `u16::MAX`. Exposed as two read-only
+/// registers, high byte at `reg` 0 and low byte at `reg` 1 (matching the
+/// big-endian convention used everywhere else in the VM).
+pub struct Timer {
+    value: u16,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self { value: 0 }
+    }
+}
+
+impl Device for Timer {
+    fn read(&mut self, reg: u16) -> Result<u8, Trap> {
+        let bytes = self.value.to_be_bytes();
+        match reg {
+            0 => Ok(bytes[0]),
+            1 => Ok(bytes[1]),
+            _ => Err(Trap::MemoryFault),
+        }
+    }
+
+    fn write(&mut self, _reg: u16, _value: u8) -> Result<(), Trap> {
+        // Read-only; writes are ignored.
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.value = self.value.wrapping_add(1);
+    }
+}
+
+/// Adapts a [`Host`]'s character I/O into the `Device` interface, so
+/// `Core::mem_read`/`mem_write` dispatch the console through the exact same
+/// `read`/`write` calls — and the same `DeviceBus::attach` — as any other
+/// peripheral instead of hand-rolling a separate code path for it.
+///
+/// The bus stores devices as `Box<dyn Device + 'static>`, which can't borrow
+/// `Core`'s `host` field directly, so `Core` hands this a cloned `Rc<RefCell<H>>`
+/// handle onto the same host instead of the host itself.
+pub struct CharDevice<H: Host> {
+    host: Rc<RefCell<H>>,
+}
+
+impl<H: Host> CharDevice<H> {
+    pub fn new(host: Rc<RefCell<H>>) -> Self {
+        Self { host }
+    }
+}
+
+impl<H: Host + 'static> Device for CharDevice<H> {
+    fn read(&mut self, _reg: u16) -> Result<u8, Trap> {
+        self.host.borrow_mut().getchar()
+    }
+
+    fn write(&mut self, _reg: u16, value: u8) -> Result<(), Trap> {
+        char::from_u32(value as _).ok_or(Trap::InvalidChar)?;
+        self.host.borrow_mut().putchar(value)
+    }
+}
@@ -0,0 +1,59 @@
+//! Decodes bytes back into the mnemonics `asm` understands.
+//!
+//! Mirrors the decoding rules in `Core::step`: the opcode is the high
+//! nibble, the two register operands are the low nibble, and an address
+//! short follows only when `operand_format` says so. Mnemonics and operand
+//! counts come from the `instructions.in`-generated tables, so this stays
+//! in sync with `step` and `asm` automatically.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{opcode_name, operand_format, OperandFormat};
+
+/// Decodes a single instruction starting at `ip`, returning its text and the
+/// `ip` of the instruction that follows it.
+pub fn decode_one(memory: &[u8], ip: u16) -> (String, u16) {
+    let instr = memory[ip as usize];
+    let opcode = (instr >> 4) & 0xF;
+    let r_a = (instr >> 2) & 0x3;
+    let r_b = instr & 0x3;
+    let mut next = ip.wrapping_add(1);
+
+    let operands = match operand_format(opcode) {
+        OperandFormat::None => String::new(),
+        OperandFormat::Reg1 => format!(" r{}", r_a),
+        OperandFormat::Reg2 => format!(" r{} r{}", r_a, r_b),
+        OperandFormat::Reg1Addr => {
+            let addr = read_addr(memory, next);
+            next = next.wrapping_add(2);
+            format!(" r{} {:#06x}", r_a, addr)
+        }
+        OperandFormat::Reg2Addr => {
+            let addr = read_addr(memory, next);
+            next = next.wrapping_add(2);
+            format!(" r{} r{} {:#06x}", r_a, r_b, addr)
+        }
+    };
+
+    (format!("{}{}", opcode_name(opcode), operands), next)
+}
+
+fn read_addr(memory: &[u8], at: u16) -> u16 {
+    let a = memory[at as usize];
+    let b = memory[at.wrapping_add(1) as usize];
+    u16::from_be_bytes([a, b])
+}
+
+/// Decodes every instruction from `start` up to (and not including) `end`.
+pub fn decode_range(memory: &[u8], start: u16, end: u16) -> Vec<(u16, String)> {
+    let mut out = Vec::new();
+    let mut ip = start;
+    while ip < end {
+        let (text, next) = decode_one(memory, ip);
+        out.push((ip, text));
+        ip = next;
+    }
+    out
+}
@@ -0,0 +1,54 @@
+//! The interface `Core` talks to for character I/O.
+//!
+//! Keeping `Core` generic over `Host` instead of calling `print!`/`stdin()`
+//! directly means the engine doesn't care whether it's driven by a real
+//! terminal, a scripted test harness, or a `no_std` host with no terminal
+//! at all.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::trap::Trap;
+
+pub trait Host {
+    /// Blocks for and returns the next input byte.
+    fn getchar(&mut self) -> Result<u8, Trap>;
+    /// Writes a single output byte.
+    fn putchar(&mut self, byte: u8) -> Result<(), Trap>;
+    /// Receives one line of execution trace. No-op unless overridden.
+    fn trace(&mut self, _line: &str) {}
+}
+
+/// A `Host` backed by fixed in-memory buffers instead of a terminal: input
+/// bytes are consumed in order, output bytes are collected. Lets tests and
+/// fuzz targets drive `Core` deterministically without touching stdio.
+pub struct BufferHost {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+impl BufferHost {
+    pub fn new(input: &[u8]) -> Self {
+        Self {
+            input: input.iter().copied().collect(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+}
+
+impl Host for BufferHost {
+    fn getchar(&mut self) -> Result<u8, Trap> {
+        // No more scripted input looks like the guest being asked to stop,
+        // same as an interactive Ctrl+C.
+        self.input.pop_front().ok_or(Trap::Interrupt)
+    }
+
+    fn putchar(&mut self, byte: u8) -> Result<(), Trap> {
+        self.output.push(byte);
+        Ok(())
+    }
+}
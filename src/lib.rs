@@ -0,0 +1,356 @@
+//! The thingamajig VM: opcode decode/execute, the trap/fault subsystem, the
+//! memory-mapped device bus, the assembler and the disassembler.
+//!
+//! This crate is `no_std` (plus `alloc` for the `Vec`/`Box`/`String` the
+//! device bus, assembler and disassembler need): `Core` is generic over
+//! [`Host`] instead of touching stdio directly, so the engine can be
+//! embedded in another program, driven by a fixed-buffer test harness, or
+//! fuzzed without dragging in a terminal or even an OS. The interactive
+//! `--debug` REPL needs real stdin/stdout, which has no `no_std`
+//! equivalent, so it isn't part of this crate — it lives in `src/debug.rs`
+//! as a module of the `src/main.rs` terminal frontend instead, built on top
+//! of the public accessors `Core` exposes for it.
+#![no_std]
+
+extern crate alloc;
+
+pub mod asm;
+mod device;
+pub mod disasm;
+mod host;
+pub mod trap;
+
+use core::cell::RefCell;
+use core::ops::RangeInclusive;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+
+use device::{CharDevice, DeviceBus, Timer};
+pub use host::{BufferHost, Host};
+pub use trap::{RunOutcome, Trap};
+
+/// Size of `Core`'s address space: every `u16` address from `0x0000` to
+/// `0xFFFF` must be a valid index, so this is `u16::MAX + 1`, not `u16::MAX`.
+const MEM_SIZE: usize = u16::MAX as usize + 1;
+
+// Generated by build.rs from `instructions.in`: the OP_* opcode constants
+// plus the `OperandFormat`/`opcode_name`/`opcode_for`/`operand_format`
+// tables that `step`, `asm` and `disasm` all consume.
+include!(concat!(env!("OUT_DIR"), "/instructions.rs"));
+
+#[derive(Debug)]
+struct Registers {
+    ip: u16,
+    rp: u16,
+    /// Faulting `ip`, saved here when a trap is raised so a guest handler
+    /// can inspect (or return to) the instruction that caused it.
+    tp: u16,
+    r0: u8,
+    r1: u8,
+    r2: u8,
+    r3: u8,
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Self {
+            ip: 0,
+            rp: 0,
+            tp: 0,
+            r0: 0,
+            r1: 0,
+            r2: 0,
+            r3: 0,
+        }
+    }
+
+    pub fn get(&self, r: u8) -> Result<u8, Trap> {
+        match r {
+            0 => Ok(self.r0),
+            1 => Ok(self.r1),
+            2 => Ok(self.r2),
+            3 => Ok(self.r3),
+            _ => Err(Trap::InvalidRegister),
+        }
+    }
+
+    pub fn set(&mut self, r: u8, value: u8) -> Result<(), Trap> {
+        match r {
+            0 => self.r0 = value,
+            1 => self.r1 = value,
+            2 => self.r2 = value,
+            3 => self.r3 = value,
+            _ => return Err(Trap::InvalidRegister),
+        }
+        Ok(())
+    }
+}
+
+pub struct Core<H: Host> {
+    memory: [u8; MEM_SIZE],
+    regs: Registers,
+    is_halted: bool,
+    bus: DeviceBus,
+    host: Rc<RefCell<H>>,
+    trace: bool,
+}
+
+impl<H: Host + 'static> Core<H> {
+    const DEV_CHAR: u16 = 0xFFFF;
+    const DEV_TIMER: RangeInclusive<u16> = 0xFFFB..=0xFFFC;
+    /// Two bytes holding the big-endian address of the installed trap
+    /// handler. `0x0000` means "no handler installed".
+    const TRAP_VECTOR: u16 = 0xFFFD;
+
+    pub fn new(host: H) -> Self {
+        let host = Rc::new(RefCell::new(host));
+
+        let mut bus = DeviceBus::new();
+        bus.attach(Self::DEV_TIMER, Box::new(Timer::new()));
+        bus.attach(Self::DEV_CHAR..=Self::DEV_CHAR, Box::new(CharDevice::new(Rc::clone(&host))));
+
+        Self {
+            memory: [0; MEM_SIZE],
+            regs: Registers::new(),
+            is_halted: false,
+            bus,
+            host,
+            trace: false,
+        }
+    }
+
+    /// Enables or disables the execution trace sent to [`Host::trace`]: the
+    /// disassembled instruction before each step and a `REGS: ...` dump
+    /// after it. Off by default so normal runs are quiet.
+    pub fn set_trace(&mut self, on: bool) {
+        self.trace = on;
+    }
+
+    fn mem_write(&mut self, addr: u16, value: u8) -> Result<(), Trap> {
+        if self.bus.contains(addr) {
+            self.bus.write(addr, value)
+        } else {
+            self.memory[addr as usize] = value;
+            Ok(())
+        }
+    }
+
+    fn mem_read(&mut self, addr: u16) -> Result<u8, Trap> {
+        if self.bus.contains(addr) {
+            self.bus.read(addr)
+        } else {
+            Ok(self.memory[addr as usize])
+        }
+    }
+
+    /// Copies `data` into memory starting at address 0. `data` longer than
+    /// the address space is truncated rather than panicking; returns how
+    /// many bytes were actually loaded.
+    pub fn load(&mut self, data: &[u8]) -> usize {
+        let len = data.len().min(MEM_SIZE);
+        self.memory[..len].copy_from_slice(&data[..len]);
+        len
+    }
+
+    /// Saves the faulting `ip` and jumps to the installed trap handler.
+    /// If no handler is installed, halts the VM and returns the trap so
+    /// the caller can report it instead of unwinding the host process.
+    fn raise(&mut self, fault_ip: u16, trap: Trap) -> Result<(), Trap> {
+        self.regs.tp = fault_ip;
+        let handler = u16::from_be_bytes([
+            self.memory[Self::TRAP_VECTOR as usize],
+            self.memory[Self::TRAP_VECTOR as usize + 1],
+        ]);
+        if handler != 0 {
+            self.regs.ip = handler;
+            Ok(())
+        } else {
+            self.is_halted = true;
+            Err(trap)
+        }
+    }
+
+    pub fn step(&mut self) -> Result<(), Trap> {
+        self.bus.tick();
+
+        let fault_ip = self.regs.ip;
+        if self.trace {
+            let (text, _) = disasm::decode_one(&self.memory, fault_ip);
+            self.host.borrow_mut().trace(&format!("{:#06x}  {}", fault_ip, text));
+        }
+
+        let instr = self.next_byte();
+        let opcode = (instr >> 4) & 0xF;
+
+        let r_a = (instr >> 2) & 0x3;
+        let r_b = instr & 0x3;
+        let addr = if operand_format(opcode).takes_addr() { self.next_short() } else { 0 };
+
+        let result = match self.execute(opcode, r_a, r_b, addr) {
+            Ok(()) => Ok(()),
+            Err(trap) => self.raise(fault_ip, trap),
+        };
+
+        if self.trace {
+            self.host.borrow_mut().trace(&format!("REGS: {:x?}", self.regs));
+        }
+
+        result
+    }
+
+    /// Runs until the program halts, traps unhandled, or `max_steps`
+    /// instructions have executed, whichever comes first. Safe to call on
+    /// untrusted bytecode: it never panics or runs away. It blocks only if
+    /// the `Host`'s `getchar` blocks (a `BufferHost` never does).
+    pub fn run_bounded(&mut self, max_steps: u32) -> RunOutcome {
+        for _ in 0..max_steps {
+            if self.is_halted {
+                return RunOutcome::Halted;
+            }
+            if let Err(trap) = self.step() {
+                return RunOutcome::Trapped(trap);
+            }
+        }
+        if self.is_halted {
+            RunOutcome::Halted
+        } else {
+            RunOutcome::StepLimitReached
+        }
+    }
+
+    fn execute(&mut self, opcode: u8, r_a: u8, r_b: u8, addr: u16) -> Result<(), Trap> {
+        match opcode {
+            OP_HALT => self.is_halted = true, //HALT
+            OP_RET => self.regs.ip = self.regs.rp,
+            OP_SHL => { // SHL
+                let value = self.regs.get(r_a)?;
+                self.regs.set(r_a, value.wrapping_shl(1))?;
+            },
+            OP_SHR => { // SHR
+                let value = self.regs.get(r_a)?;
+                self.regs.set(r_a, value.wrapping_shr(1))?;
+            },
+            OP_ROL => { // ROL
+                let value = self.regs.get(r_a)?;
+                self.regs.set(r_a, value.rotate_left(1))?;
+            },
+            OP_ROR => { // ROR
+                let value = self.regs.get(r_a)?;
+                self.regs.set(r_a, value.rotate_right(1))?;
+            },
+            OP_NAND => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                self.regs.set(r_a, !(val_a & val_b))?;
+            },
+            OP_AND => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                self.regs.set(r_a, val_a & val_b)?;
+            },
+            OP_OR => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                self.regs.set(r_a, val_a | val_b)?;
+            },
+            OP_XOR => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                self.regs.set(r_a, val_a ^ val_b)?;
+            },
+            OP_LOAD => {
+                let value = self.mem_read(addr)?;
+                self.regs.set(r_a, value)?;
+            },
+            OP_STOR => {
+                let value = self.regs.get(r_a)?;
+                self.mem_write(addr, value)?;
+            },
+            OP_CREQ => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                if val_a == val_b {
+                    self.regs.rp = self.regs.ip;
+                    self.regs.ip = addr;
+                }
+            },
+            OP_CRNE => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                if val_a != val_b {
+                    self.regs.rp = self.regs.ip;
+                    self.regs.ip = addr;
+                }
+            },
+            OP_BREQ => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                if val_a == val_b {
+                    self.regs.ip = addr;
+                }
+            },
+            OP_BRNE => {
+                let val_a = self.regs.get(r_a)?;
+                let val_b = self.regs.get(r_b)?;
+                if val_a != val_b {
+                    self.regs.ip = addr;
+                }
+            },
+            _ => return Err(Trap::UnknownOpcode),
+        }
+
+        Ok(())
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let value = self.memory[self.regs.ip as usize];
+        self.regs.ip = self.regs.ip.wrapping_add(1);
+        value
+    }
+
+    fn next_short(&mut self) -> u16 {
+        let a = self.next_byte();
+        let b = self.next_byte();
+        u16::from_be_bytes([a,b])
+    }
+
+    pub fn ip(&self) -> u16 {
+        self.regs.ip
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.is_halted
+    }
+
+    /// The faulting `ip` saved by the most recent trap, for callers that
+    /// want to report it without depending on the internal `Registers` type.
+    pub fn trap_register(&self) -> u16 {
+        self.regs.tp
+    }
+
+    /// A debug-formatted dump of the register file, for callers (like the
+    /// `--debug` REPL) that want to display it without depending on the
+    /// internal `Registers` type.
+    pub fn register_snapshot(&self) -> String {
+        format!("{:x?}", self.regs)
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Reads a memory cell directly, bypassing the device bus. Used by the
+    /// debugger to inspect RAM without side effects like blocking for a
+    /// keypress.
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.memory[addr as usize]
+    }
+
+    /// Writes a memory cell directly, bypassing the device bus.
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        self.memory[addr as usize] = value;
+    }
+}
@@ -0,0 +1,34 @@
+//! Faults raised by `Core::step` instead of panicking.
+//!
+//! A trap saves the faulting instruction pointer into a dedicated trap
+//! register and jumps through a handler address installed by the guest
+//! program in a reserved vector near the top of memory, the same way a
+//! software interrupt works. If no handler is installed the VM halts
+//! instead of unwinding the host process.
+
+/// A fault raised while executing an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    /// A register operand referred to a register that does not exist.
+    InvalidRegister,
+    /// A byte written to the character device was not a valid `char`.
+    InvalidChar,
+    /// The host requested the guest stop (e.g. Ctrl+C at the terminal).
+    Interrupt,
+    /// An address operand referred to memory outside the VM's address space.
+    MemoryFault,
+    /// The decoded opcode has no defined behavior.
+    UnknownOpcode,
+}
+
+/// The result of running a `Core` for a bounded number of instructions via
+/// `Core::run_bounded`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The program executed `HALT` (or otherwise set `is_halted`).
+    Halted,
+    /// The instruction budget was exhausted before the program halted.
+    StepLimitReached,
+    /// An unhandled trap stopped execution.
+    Trapped(Trap),
+}